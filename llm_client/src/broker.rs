@@ -0,0 +1,30 @@
+//! The interface `Tool` implementations (`ReferenceFilterBroker`,
+//! `GoogleStudioPlanGenerator`, ...) depend on to get a completion, decoupled
+//! from any one implementation so [`crate::test_support::FakeLLMBroker`] can
+//! stand in for a live broker in tests.
+
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+
+use crate::clients::types::{
+    LLMClientCompletionRequest, LLMClientCompletionResponse, LLMClientError,
+};
+use crate::provider::{LLMProvider, LLMProviderAPIKeys};
+
+/// Dispatches a completion request to whichever provider `provider` names,
+/// streaming chunks to `sender` as they arrive and returning the final
+/// accumulated answer. `metadata` is forwarded to the underlying client for
+/// logging/tracing (e.g. the `event_type`/`root_id` pair every call site
+/// threads through).
+#[async_trait]
+pub trait Broker: Send + Sync {
+    async fn stream_completion(
+        &self,
+        api_key: LLMProviderAPIKeys,
+        request: LLMClientCompletionRequest,
+        provider: LLMProvider,
+        metadata: HashMap<String, String>,
+        sender: tokio::sync::mpsc::UnboundedSender<LLMClientCompletionResponse>,
+    ) -> Result<String, LLMClientError>;
+}
@@ -5,33 +5,211 @@ use async_openai::{
     types::{
         ChatCompletionRequestAssistantMessageArgs, ChatCompletionRequestMessage,
         ChatCompletionRequestSystemMessageArgs, ChatCompletionRequestUserMessageArgs,
-        CreateChatCompletionRequestArgs, FunctionCall, Role,
+        ChatCompletionToolArgs, ChatCompletionToolType, CreateChatCompletionRequestArgs,
+        FunctionCall, FunctionObjectArgs,
     },
     Client,
 };
 use async_trait::async_trait;
 use futures::StreamExt;
+use std::collections::BTreeMap;
+use std::time::Duration;
 
 use crate::provider::LLMProviderAPIKeys;
+use crate::token_counter;
 
 use super::types::{
     LLMClient, LLMClientCompletionRequest, LLMClientCompletionResponse, LLMClientError,
-    LLMClientMessage, LLMClientRole, LLMType,
+    LLMClientMessage, LLMClientRole, LLMClientToolCall, LLMClientUsage, LLMType,
 };
 
+/// Accumulates the incrementally-streamed `delta.tool_calls` fragments for a
+/// single tool call (identified by its index in the response) into a
+/// complete `(id, name, arguments)` triple. OpenAI streams the name and id
+/// once, up front, then dribbles the JSON `arguments` string out a few
+/// characters at a time across subsequent chunks.
+#[derive(Default)]
+struct ToolCallAccumulator {
+    id: Option<String>,
+    name: Option<String>,
+    arguments: String,
+}
+
+/// Folds a chunk's `tool_calls` deltas into the per-index accumulators and
+/// returns the tool calls assembled so far, in index order.
+fn accumulate_tool_calls(
+    accumulators: &mut BTreeMap<i32, ToolCallAccumulator>,
+    tool_call_chunks: &[async_openai::types::ChatCompletionMessageToolCallChunk],
+) -> Vec<LLMClientToolCall> {
+    for chunk in tool_call_chunks {
+        let accumulator = accumulators.entry(chunk.index).or_default();
+        if let Some(id) = &chunk.id {
+            accumulator.id = Some(id.to_owned());
+        }
+        if let Some(function) = &chunk.function {
+            if let Some(name) = &function.name {
+                accumulator.name = Some(name.to_owned());
+            }
+            if let Some(arguments) = &function.arguments {
+                accumulator.arguments.push_str(arguments);
+            }
+        }
+    }
+    accumulators
+        .values()
+        .filter_map(|accumulator| {
+            Some(LLMClientToolCall::new(
+                accumulator.id.clone().unwrap_or_default(),
+                accumulator.name.clone()?,
+                accumulator.arguments.clone(),
+            ))
+        })
+        .collect()
+}
+
 enum OpenAIClientType {
     AzureClient(Client<AzureConfig>),
     OpenAIClient(Client<OpenAIConfig>),
 }
 
-pub struct OpenAIClient {}
+/// Connection and retry policy for provider requests: how long to wait for a
+/// connection/response before giving up, how many times to retry a
+/// transient failure, and how long to back off between retries. Streaming
+/// used to die on the first 429/5xx and hand back whatever had been
+/// buffered so far with no way to tell a caller apart from a clean finish;
+/// this makes that behaviour configurable and, for genuinely transient
+/// failures, recoverable.
+#[derive(Debug, Clone)]
+pub struct RequestPolicy {
+    connect_timeout: Duration,
+    request_timeout: Duration,
+    max_retries: u32,
+    base_backoff: Duration,
+    proxy: Option<String>,
+}
+
+impl Default for RequestPolicy {
+    fn default() -> Self {
+        Self {
+            connect_timeout: Duration::from_secs(10),
+            request_timeout: Duration::from_secs(120),
+            max_retries: 3,
+            base_backoff: Duration::from_millis(500),
+            proxy: None,
+        }
+    }
+}
+
+impl RequestPolicy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_connect_timeout(mut self, connect_timeout: Duration) -> Self {
+        self.connect_timeout = connect_timeout;
+        self
+    }
+
+    pub fn with_request_timeout(mut self, request_timeout: Duration) -> Self {
+        self.request_timeout = request_timeout;
+        self
+    }
+
+    pub fn with_max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    pub fn with_base_backoff(mut self, base_backoff: Duration) -> Self {
+        self.base_backoff = base_backoff;
+        self
+    }
+
+    /// HTTP or SOCKS5 proxy url (e.g. `http://localhost:8080`,
+    /// `socks5://localhost:1080`), forwarded to the underlying `reqwest`
+    /// client for every request this client makes.
+    pub fn with_proxy(mut self, proxy: impl Into<String>) -> Self {
+        self.proxy = Some(proxy.into());
+        self
+    }
+
+    fn build_http_client(&self) -> Result<reqwest::Client, LLMClientError> {
+        let mut builder = reqwest::Client::builder()
+            .connect_timeout(self.connect_timeout)
+            .timeout(self.request_timeout);
+        if let Some(proxy) = &self.proxy {
+            builder = builder.proxy(
+                reqwest::Proxy::all(proxy).map_err(LLMClientError::ReqwestError)?,
+            );
+        }
+        builder.build().map_err(LLMClientError::ReqwestError)
+    }
+
+    /// `base_backoff * 2^attempt`, so repeated 429s/5xxs back off
+    /// exponentially instead of hammering the provider at a fixed interval.
+    fn backoff_for_attempt(&self, attempt: u32) -> Duration {
+        self.base_backoff * 2u32.saturating_pow(attempt)
+    }
+
+    /// Whether `err` is worth retrying: connection resets, timeouts, and
+    /// 429/5xx responses are transient; anything else (bad request, auth,
+    /// parsing) will just fail the same way again.
+    fn is_retryable(err: &async_openai::error::OpenAIError) -> bool {
+        match err {
+            async_openai::error::OpenAIError::Reqwest(reqwest_err) => {
+                reqwest_err.is_timeout()
+                    || reqwest_err.is_connect()
+                    || reqwest_err
+                        .status()
+                        .map(|status| status.as_u16() == 429 || status.is_server_error())
+                        .unwrap_or(false)
+            }
+            async_openai::error::OpenAIError::ApiError(api_err) => api_err
+                .code
+                .as_deref()
+                .map(|code| code == "429" || code.starts_with('5'))
+                .unwrap_or(false),
+            _ => false,
+        }
+    }
+}
+
+pub struct OpenAIClient {
+    policy: RequestPolicy,
+}
+
+impl Default for OpenAIClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 impl OpenAIClient {
     pub fn new() -> Self {
-        Self {}
+        Self {
+            policy: RequestPolicy::default(),
+        }
     }
 
-    pub fn model(&self, model: &LLMType) -> Option<String> {
+    pub fn with_policy(mut self, policy: RequestPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Resolves the upstream model string for `model`. `overrides`, when
+    /// present, is the user-supplied `LLMType -> upstream model string`
+    /// mapping carried on an `OpenAICompatible` provider config; it's
+    /// consulted first so operators can point at any OpenAI-protocol
+    /// endpoint (local llama.cpp servers, Together, Fireworks, Groq, ...)
+    /// without us having to special-case every one of them here.
+    pub fn model(
+        &self,
+        model: &LLMType,
+        overrides: Option<&std::collections::HashMap<LLMType, String>>,
+    ) -> Option<String> {
+        if let Some(name) = overrides.and_then(|overrides| overrides.get(model)) {
+            return Some(name.to_owned());
+        }
         match model {
             LLMType::GPT3_5_16k => Some("gpt-3.5-turbo-16k-0613".to_owned()),
             LLMType::Gpt4 => Some("gpt-4-0613".to_owned()),
@@ -40,7 +218,6 @@ impl OpenAIClient {
             LLMType::Gpt4O => Some("gpt-4o".to_owned()),
             LLMType::Gpt4OMini => Some("gpt-4o-mini".to_owned()),
             LLMType::DeepSeekCoder33BInstruct => Some("deepseek-coder-33b".to_owned()),
-            _ => None,
         }
     }
 
@@ -49,52 +226,60 @@ impl OpenAIClient {
         messages: &[LLMClientMessage],
     ) -> Result<Vec<ChatCompletionRequestMessage>, LLMClientError> {
         let formatted_messages = messages
-            .into_iter()
+            .iter()
             .map(|message| {
                 let role = message.role();
                 match role {
                     LLMClientRole::User => ChatCompletionRequestUserMessageArgs::default()
-                        .role(Role::User)
                         .content(message.content().to_owned())
                         .build()
-                        .map(|message| ChatCompletionRequestMessage::User(message))
-                        .map_err(|e| LLMClientError::OpenAPIError(e)),
+                        .map(ChatCompletionRequestMessage::User)
+                        .map_err(LLMClientError::OpenAPIError),
                     LLMClientRole::System => ChatCompletionRequestSystemMessageArgs::default()
-                        .role(Role::System)
                         .content(message.content().to_owned())
                         .build()
-                        .map(|message| ChatCompletionRequestMessage::System(message))
-                        .map_err(|e| LLMClientError::OpenAPIError(e)),
-                    // TODO(skcd): This might be wrong, but for now its okay as we
-                    // do not use these branches at all
-                    LLMClientRole::Assistant => match message.get_function_call() {
-                        Some(function_call) => ChatCompletionRequestAssistantMessageArgs::default()
-                            .role(Role::Function)
-                            .function_call(FunctionCall {
-                                name: function_call.name().to_owned(),
-                                arguments: function_call.arguments().to_owned(),
-                            })
-                            .build()
-                            .map(|message| ChatCompletionRequestMessage::Assistant(message))
-                            .map_err(|e| LLMClientError::OpenAPIError(e)),
-                        None => ChatCompletionRequestAssistantMessageArgs::default()
-                            .role(Role::Assistant)
-                            .content(message.content().to_owned())
-                            .build()
-                            .map(|message| ChatCompletionRequestMessage::Assistant(message))
-                            .map_err(|e| LLMClientError::OpenAPIError(e)),
+                        .map(ChatCompletionRequestMessage::System)
+                        .map_err(LLMClientError::OpenAPIError),
+                    // an assistant turn which made tool calls carries them as
+                    // `tool_calls` (the first-class path); a turn from a
+                    // provider without tool-calling support may still only
+                    // have the legacy singular `function_call`
+                    LLMClientRole::Assistant => match message.get_tool_calls() {
+                        Some(tool_calls) if !tool_calls.is_empty() => {
+                            ChatCompletionRequestAssistantMessageArgs::default()
+                                .tool_calls(Self::tool_calls_to_request(tool_calls))
+                                .build()
+                                .map(ChatCompletionRequestMessage::Assistant)
+                                .map_err(LLMClientError::OpenAPIError)
+                        }
+                        _ => match message.get_function_call() {
+                            Some(function_call) => {
+                                ChatCompletionRequestAssistantMessageArgs::default()
+                                    .function_call(FunctionCall {
+                                        name: function_call.name().to_owned(),
+                                        arguments: function_call.arguments().to_owned(),
+                                    })
+                                    .build()
+                                    .map(ChatCompletionRequestMessage::Assistant)
+                                    .map_err(LLMClientError::OpenAPIError)
+                            }
+                            None => ChatCompletionRequestAssistantMessageArgs::default()
+                                .content(message.content().to_owned())
+                                .build()
+                                .map(ChatCompletionRequestMessage::Assistant)
+                                .map_err(LLMClientError::OpenAPIError),
+                        },
                     },
                     LLMClientRole::Function => match message.get_function_call() {
                         Some(function_call) => ChatCompletionRequestAssistantMessageArgs::default()
-                            .role(Role::Function)
                             .content(message.content().to_owned())
                             .function_call(FunctionCall {
                                 name: function_call.name().to_owned(),
                                 arguments: function_call.arguments().to_owned(),
                             })
                             .build()
-                            .map(|message| ChatCompletionRequestMessage::Assistant(message))
-                            .map_err(|e| LLMClientError::OpenAPIError(e)),
+                            .map(ChatCompletionRequestMessage::Assistant)
+                            .map_err(LLMClientError::OpenAPIError),
                         None => Err(LLMClientError::FunctionCallNotPresent),
                     },
                 }
@@ -105,30 +290,63 @@ impl OpenAIClient {
             .collect::<Result<Vec<ChatCompletionRequestMessage>, LLMClientError>>()
     }
 
+    /// Converts the structured tool calls an assistant turn made into the
+    /// `async-openai` wire shape, so a follow-up turn can be sent with the
+    /// full tool-calling history intact instead of flattening it to prose.
+    fn tool_calls_to_request(
+        tool_calls: &[LLMClientToolCall],
+    ) -> Vec<async_openai::types::ChatCompletionMessageToolCall> {
+        tool_calls
+            .iter()
+            .map(|tool_call| async_openai::types::ChatCompletionMessageToolCall {
+                id: tool_call.id().to_owned(),
+                r#type: ChatCompletionToolType::Function,
+                function: FunctionCall {
+                    name: tool_call.name().to_owned(),
+                    arguments: tool_call.arguments().to_owned(),
+                },
+            })
+            .collect()
+    }
+
+    /// Builds the `tools` the model is allowed to call from the tool/function
+    /// schemas attached to the request. Returns an empty vec when the
+    /// request doesn't use tool-calling, so callers can skip setting `tools`
+    /// on the request builder entirely (some providers reject an empty
+    /// `tools` array).
+    fn tools(
+        &self,
+        tools: &[super::types::LLMClientToolSchema],
+    ) -> Result<Vec<async_openai::types::ChatCompletionTool>, LLMClientError> {
+        tools
+            .iter()
+            .map(|tool| {
+                let function = FunctionObjectArgs::default()
+                    .name(tool.name().to_owned())
+                    .description(tool.description().to_owned())
+                    .parameters(tool.parameters().to_owned())
+                    .build()
+                    .map_err(LLMClientError::OpenAPIError)?;
+                ChatCompletionToolArgs::default()
+                    .r#type(ChatCompletionToolType::Function)
+                    .function(function)
+                    .build()
+                    .map_err(LLMClientError::OpenAPIError)
+            })
+            .collect()
+    }
+
     fn generate_openai_client(
         &self,
         api_key: LLMProviderAPIKeys,
-        llm_model: &LLMType,
     ) -> Result<OpenAIClientType, LLMClientError> {
-        // special escape hatch for deepseek-coder-33b
-        if matches!(llm_model, LLMType::DeepSeekCoder33BInstruct) {
-            // if we have deepseek coder 33b right now, then we should return an openai
-            // client right here, this is a hack to get things working and the provider
-            // needs to be updated to support this
-            return match api_key {
-                LLMProviderAPIKeys::OpenAIAzureConfig(api_key) => {
-                    let config = OpenAIConfig::new()
-                        .with_api_key(api_key.api_key)
-                        .with_api_base(api_key.api_base);
-                    Ok(OpenAIClientType::OpenAIClient(Client::with_config(config)))
-                }
-                _ => Err(LLMClientError::WrongAPIKeyType),
-            };
-        }
+        let http_client = self.policy.build_http_client()?;
         match api_key {
             LLMProviderAPIKeys::OpenAI(api_key) => {
                 let config = OpenAIConfig::new().with_api_key(api_key.api_key);
-                Ok(OpenAIClientType::OpenAIClient(Client::with_config(config)))
+                Ok(OpenAIClientType::OpenAIClient(
+                    Client::with_config(config).with_http_client(http_client),
+                ))
             }
             LLMProviderAPIKeys::OpenAIAzureConfig(azure_config) => {
                 let config = AzureConfig::new()
@@ -136,9 +354,24 @@ impl OpenAIClient {
                     .with_api_key(azure_config.api_key)
                     .with_deployment_id(azure_config.deployment_id)
                     .with_api_version(azure_config.api_version);
-                Ok(OpenAIClientType::AzureClient(Client::with_config(config)))
+                Ok(OpenAIClientType::AzureClient(
+                    Client::with_config(config).with_http_client(http_client),
+                ))
+            }
+            // a genuine OpenAI-compatible endpoint: any provider speaking the
+            // OpenAI protocol (local llama.cpp servers, Together, Fireworks,
+            // Groq, DeepSeek, ...) behind an arbitrary base url, with its own
+            // LLMType -> upstream model name table instead of a hard-coded
+            // per-model branch
+            LLMProviderAPIKeys::OpenAICompatible(compatible_config) => {
+                let mut config = OpenAIConfig::new().with_api_base(compatible_config.api_base);
+                if let Some(api_key) = compatible_config.api_key {
+                    config = config.with_api_key(api_key);
+                }
+                Ok(OpenAIClientType::OpenAIClient(
+                    Client::with_config(config).with_http_client(http_client),
+                ))
             }
-            _ => Err(LLMClientError::WrongAPIKeyType),
         }
     }
 }
@@ -156,90 +389,198 @@ impl LLMClient for OpenAIClient {
         sender: tokio::sync::mpsc::UnboundedSender<LLMClientCompletionResponse>,
     ) -> Result<String, LLMClientError> {
         let llm_model = request.model();
-        let model = self.model(llm_model);
+        let model_overrides = match &api_key {
+            LLMProviderAPIKeys::OpenAICompatible(compatible_config) => {
+                Some(compatible_config.model_mapping.clone())
+            }
+            _ => None,
+        };
+        let model = self.model(llm_model, model_overrides.as_ref());
         if model.is_none() {
             return Err(LLMClientError::UnSupportedModel);
         }
         let model = model.unwrap();
+        // reject the request up front if it would already blow the model's
+        // context window, instead of paying for the round trip to find out
+        token_counter::check_budget(llm_model, request.messages())
+            .map_err(|e| LLMClientError::TokenBudgetExceeded(e.to_string()))?;
         let messages = self.messages(request.messages())?;
+        let tools = self.tools(request.tools())?;
         let mut request_builder_args = CreateChatCompletionRequestArgs::default();
         let mut request_builder = request_builder_args
             .model(model.to_owned())
             .messages(messages)
             .temperature(request.temperature())
-            .stream(true);
+            .stream(true)
+            .stream_options(async_openai::types::ChatCompletionStreamOptions {
+                include_usage: true,
+            });
         if let Some(frequency_penalty) = request.frequency_penalty() {
             request_builder = request_builder.frequency_penalty(frequency_penalty);
         }
+        if !tools.is_empty() {
+            request_builder = request_builder.tools(tools);
+        }
         let request = request_builder.build()?;
         let mut buffer = String::new();
-        let client = self.generate_openai_client(api_key, llm_model)?;
+        let mut tool_call_accumulators: BTreeMap<i32, ToolCallAccumulator> = BTreeMap::new();
+        let client = self.generate_openai_client(api_key)?;
+        let policy = &self.policy;
+        let mut attempt: u32 = 0;
 
         // TODO(skcd): Bad code :| we are repeating too many things but this
         // just works and we need it right now
         match client {
-            OpenAIClientType::AzureClient(client) => {
-                let stream_maybe = client.chat().create_stream(request).await;
-                if stream_maybe.is_err() {
-                    return Err(LLMClientError::OpenAPIError(stream_maybe.err().unwrap()));
-                } else {
-                    dbg!("no error here");
-                }
-                let mut stream = stream_maybe.unwrap();
+            OpenAIClientType::AzureClient(client) => loop {
+                let stream_maybe = client.chat().create_stream(request.clone()).await;
+                let mut stream = match stream_maybe {
+                    Ok(stream) => stream,
+                    Err(err) => {
+                        if RequestPolicy::is_retryable(&err) && attempt < policy.max_retries {
+                            tokio::time::sleep(policy.backoff_for_attempt(attempt)).await;
+                            attempt += 1;
+                            continue;
+                        }
+                        return Err(LLMClientError::OpenAPIError(err));
+                    }
+                };
+                let mut retry = false;
                 while let Some(response) = stream.next().await {
                     match response {
                         Ok(response) => {
-                            let delta = response
-                                .choices
-                                .get(0)
-                                .map(|choice| choice.delta.content.to_owned())
-                                .flatten()
+                            let choice = response.choices.first();
+                            let delta = choice
+                                .and_then(|choice| choice.delta.content.to_owned())
                                 .unwrap_or("".to_owned());
-                            let _value = response
-                                .choices
-                                .get(0)
-                                .map(|choice| choice.delta.content.as_ref())
-                                .flatten();
+                            let tool_calls = choice
+                                .map(|choice| {
+                                    accumulate_tool_calls(
+                                        &mut tool_call_accumulators,
+                                        &choice.delta.tool_calls.to_owned().unwrap_or_default(),
+                                    )
+                                })
+                                .unwrap_or_default();
+                            // the usage-accounting chunk (requested via
+                            // `stream_options.include_usage`) arrives last
+                            // and has no choices at all, only a populated
+                            // `usage`
+                            let usage = response.usage.to_owned();
                             buffer.push_str(&delta);
-                            let _ = sender.send(LLMClientCompletionResponse::new(
+                            let mut completion_response = LLMClientCompletionResponse::new(
                                 buffer.to_owned(),
                                 Some(delta),
                                 model.to_owned(),
-                            ));
+                            );
+                            if !tool_calls.is_empty() {
+                                completion_response =
+                                    completion_response.with_tool_calls(tool_calls);
+                            }
+                            if let Some(usage) = usage {
+                                completion_response = completion_response.with_usage(
+                                    LLMClientUsage::new(
+                                        usage.prompt_tokens,
+                                        usage.completion_tokens,
+                                        usage.total_tokens,
+                                    ),
+                                );
+                            }
+                            let _ = sender.send(completion_response);
                         }
                         Err(err) => {
-                            dbg!(err);
-                            break;
+                            if RequestPolicy::is_retryable(&err) && attempt < policy.max_retries {
+                                tokio::time::sleep(policy.backoff_for_attempt(attempt)).await;
+                                attempt += 1;
+                                retry = true;
+                                // the retried stream regenerates the whole
+                                // completion from the start of `request`, so
+                                // whatever we'd accumulated from this
+                                // now-abandoned attempt has to go with it
+                                buffer.clear();
+                                tool_call_accumulators.clear();
+                                break;
+                            }
+                            return Err(LLMClientError::OpenAPIError(err));
                         }
                     }
                 }
-            }
-            OpenAIClientType::OpenAIClient(client) => {
-                let mut stream = client.chat().create_stream(request).await?;
+                if !retry {
+                    break;
+                }
+            },
+            OpenAIClientType::OpenAIClient(client) => loop {
+                let stream_maybe = client.chat().create_stream(request.clone()).await;
+                let mut stream = match stream_maybe {
+                    Ok(stream) => stream,
+                    Err(err) => {
+                        if RequestPolicy::is_retryable(&err) && attempt < policy.max_retries {
+                            tokio::time::sleep(policy.backoff_for_attempt(attempt)).await;
+                            attempt += 1;
+                            continue;
+                        }
+                        return Err(LLMClientError::OpenAPIError(err));
+                    }
+                };
+                let mut retry = false;
                 while let Some(response) = stream.next().await {
                     match response {
                         Ok(response) => {
-                            let response = response
-                                .choices
-                                .get(0)
-                                .ok_or(LLMClientError::FailedToGetResponse)?;
-                            let text = response.delta.content.to_owned();
-                            if let Some(text) = text {
-                                buffer.push_str(&text);
-                                let _ = sender.send(LLMClientCompletionResponse::new(
+                            let choice = response.choices.first();
+                            let text = choice.and_then(|choice| choice.delta.content.to_owned());
+                            let tool_calls = choice
+                                .map(|choice| {
+                                    accumulate_tool_calls(
+                                        &mut tool_call_accumulators,
+                                        &choice.delta.tool_calls.to_owned().unwrap_or_default(),
+                                    )
+                                })
+                                .unwrap_or_default();
+                            let usage = response.usage.to_owned();
+                            if text.is_some() || !tool_calls.is_empty() || usage.is_some() {
+                                if let Some(text) = &text {
+                                    buffer.push_str(text);
+                                }
+                                let mut completion_response = LLMClientCompletionResponse::new(
                                     buffer.to_owned(),
-                                    Some(text),
+                                    text,
                                     model.to_owned(),
-                                ));
+                                );
+                                if !tool_calls.is_empty() {
+                                    completion_response =
+                                        completion_response.with_tool_calls(tool_calls);
+                                }
+                                if let Some(usage) = usage {
+                                    completion_response = completion_response.with_usage(
+                                        LLMClientUsage::new(
+                                            usage.prompt_tokens,
+                                            usage.completion_tokens,
+                                            usage.total_tokens,
+                                        ),
+                                    );
+                                }
+                                let _ = sender.send(completion_response);
                             }
                         }
                         Err(err) => {
-                            dbg!(err);
-                            break;
+                            if RequestPolicy::is_retryable(&err) && attempt < policy.max_retries {
+                                tokio::time::sleep(policy.backoff_for_attempt(attempt)).await;
+                                attempt += 1;
+                                retry = true;
+                                // the retried stream regenerates the whole
+                                // completion from the start of `request`, so
+                                // whatever we'd accumulated from this
+                                // now-abandoned attempt has to go with it
+                                buffer.clear();
+                                tool_call_accumulators.clear();
+                                break;
+                            }
+                            return Err(LLMClientError::OpenAPIError(err));
                         }
                     }
                 }
-            }
+                if !retry {
+                    break;
+                }
+            },
         }
         Ok(buffer)
     }
@@ -256,10 +597,92 @@ impl LLMClient for OpenAIClient {
 
     async fn stream_prompt_completion(
         &self,
-        _api_key: LLMProviderAPIKeys,
-        _request: super::types::LLMClientCompletionStringRequest,
-        _sender: tokio::sync::mpsc::UnboundedSender<LLMClientCompletionResponse>,
+        api_key: LLMProviderAPIKeys,
+        request: super::types::LLMClientCompletionStringRequest,
+        sender: tokio::sync::mpsc::UnboundedSender<LLMClientCompletionResponse>,
     ) -> Result<String, LLMClientError> {
-        Err(LLMClientError::OpenAIDoesNotSupportCompletion)
+        // only a genuine OpenAI-compatible endpoint (local llama.cpp
+        // servers, the DeepSeek Coder instruct deployment, ...) exposes
+        // `/v1/completions` for raw-prompt models; chat-only providers
+        // (OpenAI proper, Azure) keep returning the existing error so
+        // callers can branch on capability instead of getting a confusing
+        // failure from the wrong endpoint
+        let model_mapping = match &api_key {
+            LLMProviderAPIKeys::OpenAICompatible(compatible_config) => {
+                compatible_config.model_mapping.clone()
+            }
+            _ => return Err(LLMClientError::OpenAIDoesNotSupportCompletion),
+        };
+
+        let llm_model = request.model();
+        let model = self
+            .model(llm_model, Some(&model_mapping))
+            .ok_or(LLMClientError::UnSupportedModel)?;
+
+        let mut request_builder_args = async_openai::types::CreateCompletionRequestArgs::default();
+        let mut request_builder = request_builder_args
+            .model(model.to_owned())
+            .prompt(async_openai::types::Prompt::String(
+                request.prompt().to_owned(),
+            ))
+            .temperature(request.temperature())
+            .stream(true);
+        if let Some(frequency_penalty) = request.frequency_penalty() {
+            request_builder = request_builder.frequency_penalty(frequency_penalty);
+        }
+        let completion_request = request_builder.build()?;
+
+        let client = match self.generate_openai_client(api_key)? {
+            OpenAIClientType::OpenAIClient(client) => client,
+            OpenAIClientType::AzureClient(_) => {
+                return Err(LLMClientError::OpenAIDoesNotSupportCompletion)
+            }
+        };
+
+        let policy = &self.policy;
+        let mut attempt: u32 = 0;
+        let mut stream = loop {
+            match client
+                .completions()
+                .create_stream(completion_request.clone())
+                .await
+            {
+                Ok(stream) => break stream,
+                Err(err) => {
+                    if RequestPolicy::is_retryable(&err) && attempt < policy.max_retries {
+                        tokio::time::sleep(policy.backoff_for_attempt(attempt)).await;
+                        attempt += 1;
+                        continue;
+                    }
+                    return Err(LLMClientError::OpenAPIError(err));
+                }
+            }
+        };
+
+        let mut buffer = String::new();
+        while let Some(response) = stream.next().await {
+            match response {
+                Ok(response) => {
+                    let text = response
+                        .choices
+                        .first()
+                        .map(|choice| choice.text.to_owned())
+                        .unwrap_or_default();
+                    if !text.is_empty() {
+                        buffer.push_str(&text);
+                        let completion_response = LLMClientCompletionResponse::new(
+                            buffer.to_owned(),
+                            Some(text),
+                            model.to_owned(),
+                        );
+                        let _ = sender.send(completion_response);
+                    }
+                }
+                Err(err) => {
+                    return Err(LLMClientError::OpenAPIError(err));
+                }
+            }
+        }
+        Ok(buffer)
     }
 }
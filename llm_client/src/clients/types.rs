@@ -0,0 +1,419 @@
+//! Provider-agnostic request/response shapes every `LLMClient` implementation
+//! (OpenAI, Azure, ...) speaks, so callers don't need to know which provider
+//! is behind an `LLMProperties`.
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+use crate::provider::{LLMProvider, LLMProviderAPIKeys};
+
+/// The logical model a request is addressed to. A provider's `OpenAIClient`
+/// (or equivalent) maps this to whatever string/deployment id the upstream
+/// API actually expects.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum LLMType {
+    GPT3_5_16k,
+    Gpt4,
+    Gpt4Turbo,
+    Gpt4_32k,
+    Gpt4O,
+    Gpt4OMini,
+    DeepSeekCoder33BInstruct,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LLMClientRole {
+    System,
+    User,
+    Assistant,
+    Function,
+}
+
+/// A single function/tool call, either made by the assistant (streamed back
+/// from the provider) or replayed into a follow-up request's history.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LLMClientToolCall {
+    id: String,
+    name: String,
+    arguments: String,
+}
+
+impl LLMClientToolCall {
+    pub fn new(id: String, name: String, arguments: String) -> Self {
+        Self {
+            id,
+            name,
+            arguments,
+        }
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn arguments(&self) -> &str {
+        &self.arguments
+    }
+}
+
+/// The legacy single `function_call` shape some providers still use instead
+/// of the first-class `tool_calls` array.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LLMClientFunctionCall {
+    name: String,
+    arguments: String,
+}
+
+impl LLMClientFunctionCall {
+    pub fn new(name: String, arguments: String) -> Self {
+        Self { name, arguments }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn arguments(&self) -> &str {
+        &self.arguments
+    }
+}
+
+/// A tool/function schema a request offers the model, in JSON-schema form.
+#[derive(Debug, Clone)]
+pub struct LLMClientToolSchema {
+    name: String,
+    description: String,
+    parameters: serde_json::Value,
+}
+
+impl LLMClientToolSchema {
+    pub fn new(
+        name: impl Into<String>,
+        description: impl Into<String>,
+        parameters: serde_json::Value,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            description: description.into(),
+            parameters,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    pub fn parameters(&self) -> &serde_json::Value {
+        &self.parameters
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct LLMClientMessage {
+    role: LLMClientRole,
+    content: String,
+    function_call: Option<LLMClientFunctionCall>,
+    tool_calls: Option<Vec<LLMClientToolCall>>,
+}
+
+impl LLMClientMessage {
+    pub fn new(role: LLMClientRole, content: String) -> Self {
+        Self {
+            role,
+            content,
+            function_call: None,
+            tool_calls: None,
+        }
+    }
+
+    pub fn system(content: String) -> Self {
+        Self::new(LLMClientRole::System, content)
+    }
+
+    pub fn user(content: String) -> Self {
+        Self::new(LLMClientRole::User, content)
+    }
+
+    pub fn assistant(content: String) -> Self {
+        Self::new(LLMClientRole::Assistant, content)
+    }
+
+    pub fn function(content: String) -> Self {
+        Self::new(LLMClientRole::Function, content)
+    }
+
+    pub fn with_function_call(mut self, function_call: LLMClientFunctionCall) -> Self {
+        self.function_call = Some(function_call);
+        self
+    }
+
+    pub fn with_tool_calls(mut self, tool_calls: Vec<LLMClientToolCall>) -> Self {
+        self.tool_calls = Some(tool_calls);
+        self
+    }
+
+    pub fn role(&self) -> &LLMClientRole {
+        &self.role
+    }
+
+    pub fn content(&self) -> &str {
+        &self.content
+    }
+
+    pub fn get_function_call(&self) -> Option<&LLMClientFunctionCall> {
+        self.function_call.as_ref()
+    }
+
+    pub fn get_tool_calls(&self) -> Option<&[LLMClientToolCall]> {
+        self.tool_calls.as_deref()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct LLMClientCompletionRequest {
+    model: LLMType,
+    messages: Vec<LLMClientMessage>,
+    temperature: f32,
+    frequency_penalty: Option<f32>,
+    tools: Vec<LLMClientToolSchema>,
+}
+
+impl LLMClientCompletionRequest {
+    pub fn new(
+        model: LLMType,
+        messages: Vec<LLMClientMessage>,
+        temperature: f32,
+        frequency_penalty: Option<f32>,
+    ) -> Self {
+        Self {
+            model,
+            messages,
+            temperature,
+            frequency_penalty,
+            tools: vec![],
+        }
+    }
+
+    /// Attaches the tool/function schemas the model is allowed to call.
+    /// Providers without tool-calling support simply ignore an empty list.
+    pub fn with_tools(mut self, tools: Vec<LLMClientToolSchema>) -> Self {
+        self.tools = tools;
+        self
+    }
+
+    pub fn model(&self) -> &LLMType {
+        &self.model
+    }
+
+    pub fn messages(&self) -> &[LLMClientMessage] {
+        &self.messages
+    }
+
+    pub fn temperature(&self) -> f32 {
+        self.temperature
+    }
+
+    pub fn frequency_penalty(&self) -> Option<f32> {
+        self.frequency_penalty
+    }
+
+    pub fn tools(&self) -> &[LLMClientToolSchema] {
+        &self.tools
+    }
+}
+
+/// A raw-prompt (non-chat) completion request, for base/instruct models
+/// served behind the `/v1/completions`-style endpoint.
+#[derive(Debug, Clone)]
+pub struct LLMClientCompletionStringRequest {
+    model: LLMType,
+    prompt: String,
+    temperature: f32,
+    frequency_penalty: Option<f32>,
+}
+
+impl LLMClientCompletionStringRequest {
+    pub fn new(
+        model: LLMType,
+        prompt: String,
+        temperature: f32,
+        frequency_penalty: Option<f32>,
+    ) -> Self {
+        Self {
+            model,
+            prompt,
+            temperature,
+            frequency_penalty,
+        }
+    }
+
+    pub fn model(&self) -> &LLMType {
+        &self.model
+    }
+
+    pub fn prompt(&self) -> &str {
+        &self.prompt
+    }
+
+    pub fn temperature(&self) -> f32 {
+        self.temperature
+    }
+
+    pub fn frequency_penalty(&self) -> Option<f32> {
+        self.frequency_penalty
+    }
+}
+
+/// Token accounting for a completion, mirrored from the provider's own
+/// `usage` block so callers can track spend without re-deriving it.
+#[derive(Debug, Clone, Copy)]
+pub struct LLMClientUsage {
+    prompt_tokens: u32,
+    completion_tokens: u32,
+    total_tokens: u32,
+}
+
+impl LLMClientUsage {
+    pub fn new(prompt_tokens: u32, completion_tokens: u32, total_tokens: u32) -> Self {
+        Self {
+            prompt_tokens,
+            completion_tokens,
+            total_tokens,
+        }
+    }
+
+    pub fn prompt_tokens(&self) -> u32 {
+        self.prompt_tokens
+    }
+
+    pub fn completion_tokens(&self) -> u32 {
+        self.completion_tokens
+    }
+
+    pub fn total_tokens(&self) -> u32 {
+        self.total_tokens
+    }
+}
+
+/// A single streamed chunk of a completion: the full answer accumulated so
+/// far and the delta this chunk added. Once a tool call has started
+/// streaming, each chunk also carries the tool calls assembled so far, whose
+/// `arguments` are complete only after the provider has sent every
+/// fragment; the usage accounting arrives on the final, usage-only chunk.
+#[derive(Debug, Clone)]
+pub struct LLMClientCompletionResponse {
+    answer_up_until_now: String,
+    delta: Option<String>,
+    model: String,
+    tool_calls: Option<Vec<LLMClientToolCall>>,
+    usage: Option<LLMClientUsage>,
+}
+
+impl LLMClientCompletionResponse {
+    pub fn new(answer_up_until_now: String, delta: Option<String>, model: String) -> Self {
+        Self {
+            answer_up_until_now,
+            delta,
+            model,
+            tool_calls: None,
+            usage: None,
+        }
+    }
+
+    pub fn with_tool_calls(mut self, tool_calls: Vec<LLMClientToolCall>) -> Self {
+        self.tool_calls = Some(tool_calls);
+        self
+    }
+
+    pub fn with_usage(mut self, usage: LLMClientUsage) -> Self {
+        self.usage = Some(usage);
+        self
+    }
+
+    pub fn answer_up_until_now(&self) -> &str {
+        &self.answer_up_until_now
+    }
+
+    pub fn delta(&self) -> Option<&str> {
+        self.delta.as_deref()
+    }
+
+    pub fn model(&self) -> &str {
+        &self.model
+    }
+
+    pub fn tool_calls(&self) -> Option<&[LLMClientToolCall]> {
+        self.tool_calls.as_deref()
+    }
+
+    pub fn usage(&self) -> Option<LLMClientUsage> {
+        self.usage
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum LLMClientError {
+    #[error("provider returned an error: {0}")]
+    OpenAPIError(#[from] async_openai::error::OpenAIError),
+    #[error("unsupported model")]
+    UnSupportedModel,
+    #[error("wrong api key type for this provider")]
+    WrongAPIKeyType,
+    #[error("expected a function call on this message but none was present")]
+    FunctionCallNotPresent,
+    #[error("failed to get a response from the provider")]
+    FailedToGetResponse,
+    #[error("this provider does not support the completions (non-chat) endpoint")]
+    OpenAIDoesNotSupportCompletion,
+    #[error("request exceeds the model's token budget: {0}")]
+    TokenBudgetExceeded(String),
+    #[error("request failed: {0}")]
+    ReqwestError(#[from] reqwest::Error),
+}
+
+/// Implemented once per provider (OpenAI, Azure, ...). `broker::Broker`
+/// dispatches to whichever implementation matches the request's
+/// `LLMProvider` rather than callers picking a client directly.
+#[async_trait]
+pub trait LLMClient: Send + Sync {
+    fn client(&self) -> &LLMProvider;
+
+    /// Streams `completion_response`s for `request` to `sender` as they
+    /// arrive, returning the fully accumulated answer once the stream ends.
+    ///
+    /// A mid-stream transport error that's worth retrying restarts the whole
+    /// completion from scratch on a fresh connection, but any chunk already
+    /// handed to `sender` from the abandoned attempt can't be recalled -
+    /// implementations reset their own accumulation (e.g. the answer-so-far
+    /// buffer) before retrying, but `sender`'s receiver will see
+    /// `answer_up_until_now` rewind backwards for that one retry. Callers
+    /// that accumulate off `sender` themselves instead of relying on
+    /// `answer_up_until_now` must be able to tolerate that rewind.
+    async fn stream_completion(
+        &self,
+        api_key: LLMProviderAPIKeys,
+        request: LLMClientCompletionRequest,
+        sender: tokio::sync::mpsc::UnboundedSender<LLMClientCompletionResponse>,
+    ) -> Result<String, LLMClientError>;
+
+    async fn completion(
+        &self,
+        api_key: LLMProviderAPIKeys,
+        request: LLMClientCompletionRequest,
+    ) -> Result<String, LLMClientError>;
+
+    async fn stream_prompt_completion(
+        &self,
+        api_key: LLMProviderAPIKeys,
+        request: LLMClientCompletionStringRequest,
+        sender: tokio::sync::mpsc::UnboundedSender<LLMClientCompletionResponse>,
+    ) -> Result<String, LLMClientError>;
+}
@@ -0,0 +1,6 @@
+pub mod broker;
+pub mod clients;
+pub mod provider;
+#[cfg(feature = "test-support")]
+pub mod test_support;
+pub mod token_counter;
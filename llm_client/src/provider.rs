@@ -0,0 +1,75 @@
+//! The providers an `LLMClient` can be pointed at, and the credentials each
+//! one needs. `LLMProvider` is the logical identity a `ModelEntry` picks;
+//! `LLMProviderAPIKeys` is the matching credential bundle a broker looks up
+//! by provider before dispatching a request.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::clients::types::LLMType;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LLMProvider {
+    OpenAI,
+    Azure,
+    OpenAICompatible,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OpenAIProviderAPIKey {
+    pub api_key: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AzureOpenAIProviderAPIKey {
+    pub deployment_id: String,
+    pub api_base: String,
+    pub api_key: String,
+    #[serde(default = "default_azure_api_version")]
+    pub api_version: String,
+}
+
+fn default_azure_api_version() -> String {
+    "2023-08-01-preview".to_owned()
+}
+
+/// Credentials for a generic OpenAI-compatible endpoint (local llama.cpp
+/// servers, Together, Fireworks, Groq, DeepSeek, ...) behind an arbitrary
+/// base url, with its own `LLMType` -> upstream model name table instead of
+/// a hard-coded per-model branch.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OpenAICompatibleConfig {
+    pub api_base: String,
+    #[serde(default)]
+    pub api_key: Option<String>,
+    #[serde(default)]
+    pub model_mapping: HashMap<LLMType, String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum LLMProviderAPIKeys {
+    OpenAI(OpenAIProviderAPIKey),
+    OpenAIAzureConfig(AzureOpenAIProviderAPIKey),
+    OpenAICompatible(OpenAICompatibleConfig),
+}
+
+impl LLMProviderAPIKeys {
+    pub fn provider(&self) -> LLMProvider {
+        match self {
+            LLMProviderAPIKeys::OpenAI(_) => LLMProvider::OpenAI,
+            LLMProviderAPIKeys::OpenAIAzureConfig(_) => LLMProvider::Azure,
+            LLMProviderAPIKeys::OpenAICompatible(_) => LLMProvider::OpenAICompatible,
+        }
+    }
+
+    /// Returns `self` if it is the credential bundle for `provider`, so
+    /// callers can `.find` the right entry in a `Vec<LLMProviderAPIKeys>`.
+    pub fn key(&self, provider: &LLMProvider) -> Option<&Self> {
+        if &self.provider() == provider {
+            Some(self)
+        } else {
+            None
+        }
+    }
+}
@@ -0,0 +1,75 @@
+//! Local pre-flight token accounting, so a request which would blow a
+//! model's context window can be rejected (or trimmed) before it ever
+//! reaches the provider.
+
+use tiktoken_rs::{cl100k_base, CoreBPE};
+
+use crate::clients::types::{LLMClientMessage, LLMType};
+
+#[derive(Debug, thiserror::Error)]
+pub enum TokenBudgetError {
+    #[error("request uses an estimated {estimated_tokens} tokens, which exceeds {model:?}'s {max_context_tokens} token context window")]
+    ContextWindowExceeded {
+        model: LLMType,
+        estimated_tokens: usize,
+        max_context_tokens: u32,
+    },
+}
+
+/// Per-message overhead the chat format adds on top of the raw content
+/// tokens (role, delimiters, ...). This mirrors OpenAI's own guidance for
+/// `cl100k_base`-tokenized chat models; it's an estimate, not an exact
+/// count, which is all a pre-flight budget check needs.
+const TOKENS_PER_MESSAGE: usize = 4;
+
+fn bpe() -> CoreBPE {
+    // gpt-3.5/gpt-4 family models all use cl100k_base; this is a reasonable
+    // default estimator for every model we talk to today.
+    cl100k_base().expect("cl100k_base ranks are bundled with tiktoken-rs")
+}
+
+/// Estimates the number of tokens `messages` will cost using a
+/// tiktoken-style BPE, so callers can budget-check a request before sending
+/// it.
+pub fn estimate_tokens(messages: &[LLMClientMessage]) -> usize {
+    let bpe = bpe();
+    messages
+        .iter()
+        .map(|message| TOKENS_PER_MESSAGE + bpe.encode_with_special_tokens(message.content()).len())
+        .sum()
+}
+
+/// The max context length we know about for a given model. `None` means we
+/// have no budget information for this model, in which case callers should
+/// skip the check rather than fail closed.
+pub fn max_context_tokens(model: &LLMType) -> Option<u32> {
+    match model {
+        LLMType::GPT3_5_16k => Some(16_000),
+        LLMType::Gpt4 => Some(8_192),
+        LLMType::Gpt4Turbo => Some(128_000),
+        LLMType::Gpt4_32k => Some(32_768),
+        LLMType::Gpt4O => Some(128_000),
+        LLMType::Gpt4OMini => Some(128_000),
+        LLMType::DeepSeekCoder33BInstruct => Some(16_000),
+    }
+}
+
+/// Estimates the token cost of `messages` for `model` and rejects it up
+/// front if it would exceed the model's known context window. Returns the
+/// estimate either way so callers can log/report it.
+pub fn check_budget(
+    model: &LLMType,
+    messages: &[LLMClientMessage],
+) -> Result<usize, TokenBudgetError> {
+    let estimated_tokens = estimate_tokens(messages);
+    if let Some(max_context_tokens) = max_context_tokens(model) {
+        if estimated_tokens > max_context_tokens as usize {
+            return Err(TokenBudgetError::ContextWindowExceeded {
+                model: *model,
+                estimated_tokens,
+                max_context_tokens,
+            });
+        }
+    }
+    Ok(estimated_tokens)
+}
@@ -1,6 +1,7 @@
 use crate::{agentic::symbol::identifier::SymbolIdentifier, chunking::text_document::Range};
 
 use super::initial_request::SymbolRequestHistoryItem;
+use super::session_store::{SessionOp, SessionStore};
 
 #[derive(Debug, Clone, serde::Serialize)]
 pub struct SymbolToEdit {
@@ -110,4 +111,43 @@ impl SymbolToEditRequest {
     pub fn history(&self) -> &[SymbolRequestHistoryItem] {
         self.history.as_slice()
     }
+
+    /// Emits this request's symbols and history into `store` as session
+    /// ops, so that concurrent edit requests against the same root converge
+    /// on one `InitialRequestData` via `SessionStore::snapshot` instead of
+    /// each caller building its own from whatever `history`/`symbols` it
+    /// happened to be constructed with.
+    ///
+    /// Call this once per logical edit request: each call stamps its ops
+    /// with a fresh lamport tick, so a retried call (even with the same
+    /// `origin_id`) is a distinct, non-deduplicated entry and will duplicate
+    /// this request's history in the resulting snapshot. As with
+    /// `SessionStore::merge_op`, a `root_id` with no session yet (i.e.
+    /// `SessionStore::create_session` hasn't been called for it) makes this
+    /// a silent no-op - the caller is expected to have created the session
+    /// up front, same as the rest of this store's API.
+    pub fn record_in_session_store(&self, store: &SessionStore, root_id: &str, origin_id: String) {
+        for history_item in &self.history {
+            store.apply_op(
+                root_id,
+                SessionOp::AddHistory {
+                    symbol: history_item.symbol_name().to_owned(),
+                    fs_file_path: history_item.fs_file_path().to_owned(),
+                    request: history_item.request().to_owned(),
+                },
+                origin_id.clone(),
+            );
+        }
+        for symbol in &self.symbols {
+            store.apply_op(
+                root_id,
+                SessionOp::MarkSymbolEdited {
+                    symbol: symbol.symbol_name().to_owned(),
+                    fs_file_path: symbol.fs_file_path().to_owned(),
+                    is_new: symbol.is_new(),
+                },
+                origin_id.clone(),
+            );
+        }
+    }
 }
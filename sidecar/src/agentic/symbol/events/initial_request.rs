@@ -59,6 +59,10 @@ impl SymbolRequestHistoryItem {
     pub fn fs_file_path(&self) -> &str {
         &self.fs_file_path
     }
+
+    pub fn request(&self) -> &str {
+        &self.request
+    }
 }
 
 #[derive(Debug, Clone, serde::Serialize)]
@@ -69,9 +73,12 @@ pub struct InitialRequestData {
     /// We operate on the full symbol instead of the
     full_symbol_request: bool,
     // This is an option for now since we for code-correctness we also send
-    // this request, but this is more tied to the original plan
-    // in the future this will be a reference to some plan object which will
-    // dynamically update the symbol edited items inside
+    // this request, but this is more tied to the original plan.
+    // Both this and `history` above are snapshots: concurrent agent runs on
+    // the same root request go through `SessionStore` instead (see
+    // `SymbolToEditRequest::record_in_session_store`), which models them as
+    // an append-only op log and produces this snapshot via
+    // `SessionStore::snapshot`.
     symbols_edited_list: Option<Vec<SymbolEditedItem>>,
 }
 
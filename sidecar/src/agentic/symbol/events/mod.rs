@@ -0,0 +1,3 @@
+pub mod edit;
+pub mod initial_request;
+pub mod session_store;
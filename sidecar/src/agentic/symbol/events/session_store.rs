@@ -0,0 +1,262 @@
+//! A collaborative, operation-log backed store for the history and
+//! edited-symbol list that feed [`InitialRequestData`]. Two concurrent agent
+//! runs (or a client reconnecting mid-session) on the same root request used
+//! to race over a single snapshot; modelling those fields as an append-only,
+//! commutative log instead lets every writer converge on the same state
+//! regardless of delivery order.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+
+use super::initial_request::{InitialRequestData, SymbolEditedItem, SymbolRequestHistoryItem};
+
+/// Monotonic position of an op in a root session's local log, used by
+/// [`SessionStore::ops_since`] so a reconnecting client can ask for only what
+/// it missed.
+pub type Seq = u64;
+
+/// Lamport logical clock. Ops are folded into a snapshot in
+/// `(lamport, origin_id)` order so two stores which received the same set of
+/// ops in different wall-clock order still converge on the same result.
+pub type LamportClock = u64;
+
+/// A single mutation to a root session's history/edited-symbol state.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub enum SessionOp {
+    AddHistory {
+        symbol: String,
+        fs_file_path: String,
+        request: String,
+    },
+    MarkSymbolEdited {
+        symbol: String,
+        fs_file_path: String,
+        is_new: bool,
+    },
+    UpdateThinking {
+        symbol: String,
+        fs_file_path: String,
+        thinking: String,
+    },
+}
+
+/// A [`SessionOp`] stamped with the logical clock and origin needed to apply
+/// it commutatively and idempotently.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct SessionOpEnvelope {
+    op: SessionOp,
+    lamport: LamportClock,
+    origin_id: String,
+}
+
+impl SessionOpEnvelope {
+    pub fn op(&self) -> &SessionOp {
+        &self.op
+    }
+
+    pub fn lamport(&self) -> LamportClock {
+        self.lamport
+    }
+
+    pub fn origin_id(&self) -> &str {
+        &self.origin_id
+    }
+}
+
+struct RootSession {
+    original_question: String,
+    plan_if_available: Option<String>,
+    full_symbol_request: bool,
+    // kept in insertion order locally, but folded for snapshots in
+    // (lamport, origin_id) order so replay is deterministic regardless of
+    // the order ops actually arrived in
+    log: Vec<(Seq, SessionOpEnvelope)>,
+    // dedupes ops we've already applied, so a duplicate delivery (e.g. a
+    // client replaying `ops_since` after a dropped ack) is a no-op
+    applied: HashSet<(String, LamportClock)>,
+    next_seq: Seq,
+    lamport_clock: LamportClock,
+}
+
+/// Keeps one append-only operation log per `root_id`. Agentic tools should
+/// emit ops here instead of mutating `history`/`symbols_edited_list` vectors
+/// directly, and read state back out via [`Self::snapshot`].
+pub struct SessionStore {
+    sessions: Mutex<HashMap<String, RootSession>>,
+}
+
+impl SessionStore {
+    pub fn new() -> Self {
+        Self {
+            sessions: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn create_session(
+        &self,
+        root_id: String,
+        original_question: String,
+        plan_if_available: Option<String>,
+        full_symbol_request: bool,
+    ) {
+        self.sessions.lock().expect("lock poisoned").insert(
+            root_id,
+            RootSession {
+                original_question,
+                plan_if_available,
+                full_symbol_request,
+                log: vec![],
+                applied: HashSet::new(),
+                next_seq: 0,
+                lamport_clock: 0,
+            },
+        );
+    }
+
+    /// Stamps `op` with a fresh local lamport tick and origin, appends it to
+    /// `root_id`'s log, and returns the envelope so callers can forward it to
+    /// other replicas. Returns `None` if `root_id` has no session.
+    pub fn apply_op(
+        &self,
+        root_id: &str,
+        op: SessionOp,
+        origin_id: String,
+    ) -> Option<SessionOpEnvelope> {
+        let mut sessions = self.sessions.lock().expect("lock poisoned");
+        let session = sessions.get_mut(root_id)?;
+        session.lamport_clock += 1;
+        let envelope = SessionOpEnvelope {
+            op,
+            lamport: session.lamport_clock,
+            origin_id,
+        };
+        Self::insert_envelope(session, envelope.clone());
+        Some(envelope)
+    }
+
+    /// Merges an envelope produced elsewhere (e.g. another agent run, or a
+    /// reconnecting client resyncing via [`Self::ops_since`]). Applying the
+    /// same envelope twice is a no-op, and applying envelopes out of arrival
+    /// order still converges because folding happens in lamport order at
+    /// snapshot time.
+    pub fn merge_op(&self, root_id: &str, envelope: SessionOpEnvelope) -> bool {
+        let mut sessions = self.sessions.lock().expect("lock poisoned");
+        let Some(session) = sessions.get_mut(root_id) else {
+            return false;
+        };
+        session.lamport_clock = session.lamport_clock.max(envelope.lamport);
+        Self::insert_envelope(session, envelope)
+    }
+
+    fn insert_envelope(session: &mut RootSession, envelope: SessionOpEnvelope) -> bool {
+        let dedup_key = (envelope.origin_id.clone(), envelope.lamport);
+        if !session.applied.insert(dedup_key) {
+            return false;
+        }
+        let seq = session.next_seq;
+        session.next_seq += 1;
+        session.log.push((seq, envelope));
+        true
+    }
+
+    /// Folds the operation log into the snapshot shape the rest of the
+    /// codebase consumes.
+    pub fn snapshot(&self, root_id: &str) -> Option<InitialRequestData> {
+        let sessions = self.sessions.lock().expect("lock poisoned");
+        let session = sessions.get(root_id)?;
+
+        let mut ordered = session
+            .log
+            .iter()
+            .map(|(_, envelope)| envelope)
+            .collect::<Vec<_>>();
+        ordered.sort_by(|a, b| (a.lamport, &a.origin_id).cmp(&(b.lamport, &b.origin_id)));
+
+        let mut history = vec![];
+        let mut edited: HashMap<(String, String), SymbolEditedItem> = HashMap::new();
+        let mut thinking: HashMap<(String, String), String> = HashMap::new();
+
+        for envelope in ordered {
+            match &envelope.op {
+                SessionOp::AddHistory {
+                    symbol,
+                    fs_file_path,
+                    request,
+                } => {
+                    history.push(SymbolRequestHistoryItem::new(
+                        symbol.clone(),
+                        fs_file_path.clone(),
+                        request.clone(),
+                    ));
+                }
+                SessionOp::MarkSymbolEdited {
+                    symbol,
+                    fs_file_path,
+                    is_new,
+                } => {
+                    let key = (symbol.clone(), fs_file_path.clone());
+                    let thinking = thinking.get(&key).cloned().unwrap_or_default();
+                    edited.insert(
+                        key,
+                        SymbolEditedItem::new(
+                            symbol.clone(),
+                            fs_file_path.clone(),
+                            *is_new,
+                            thinking,
+                        ),
+                    );
+                }
+                SessionOp::UpdateThinking {
+                    symbol,
+                    fs_file_path,
+                    thinking: updated_thinking,
+                } => {
+                    let key = (symbol.clone(), fs_file_path.clone());
+                    thinking.insert(key.clone(), updated_thinking.clone());
+                    if let Some(existing) = edited.get_mut(&key) {
+                        *existing = SymbolEditedItem::new(
+                            symbol.clone(),
+                            fs_file_path.clone(),
+                            existing.is_new(),
+                            updated_thinking.clone(),
+                        );
+                    }
+                }
+            }
+        }
+
+        let symbols_edited_list = if edited.is_empty() {
+            None
+        } else {
+            // `edited` is a HashMap, so its iteration order isn't
+            // deterministic; sort by the same key it's indexed on so two
+            // snapshots of the same log always agree.
+            let mut edited = edited.into_iter().collect::<Vec<_>>();
+            edited.sort_by(|(a, _), (b, _)| a.cmp(b));
+            Some(edited.into_iter().map(|(_, item)| item).collect())
+        };
+
+        Some(InitialRequestData::new(
+            session.original_question.clone(),
+            session.plan_if_available.clone(),
+            history,
+            session.full_symbol_request,
+            symbols_edited_list,
+        ))
+    }
+
+    /// Ops appended after `seq`, in local arrival order, for a client to
+    /// replay after reconnecting.
+    pub fn ops_since(&self, root_id: &str, seq: Seq) -> Vec<SessionOpEnvelope> {
+        let sessions = self.sessions.lock().expect("lock poisoned");
+        let Some(session) = sessions.get(root_id) else {
+            return vec![];
+        };
+        session
+            .log
+            .iter()
+            .filter(|(op_seq, _)| *op_seq > seq)
+            .map(|(_, envelope)| envelope.clone())
+            .collect()
+    }
+}
@@ -1,15 +1,18 @@
 use async_trait::async_trait;
 use futures::{stream, StreamExt};
 use llm_client::{
-    broker::LLMBroker,
-    clients::types::{LLMClientCompletionRequest, LLMClientMessage},
+    broker::Broker,
+    clients::types::{LLMClientCompletionRequest, LLMClientMessage, LLMClientToolSchema},
 };
 use std::sync::Arc;
 
 use crate::{
     agentic::{
         symbol::identifier::LLMProperties,
-        tool::{errors::ToolError, input::ToolInput, output::ToolOutput, r#type::Tool},
+        tool::{
+            errors::ToolError, input::ToolInput, output::ToolOutput, r#type::Tool,
+            transform::TransformPipeline,
+        },
     },
     chunking::types::OutlineNode,
 };
@@ -65,36 +68,121 @@ impl ReferenceFilterRequest {
     }
 }
 
+/// The confidence the model has in a [`ReferenceDecision`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReferenceDecisionConfidence {
+    Low,
+    Medium,
+    High,
+}
+
+/// A single, typed decision for whether a reference needs to change, as
+/// reported by the model via the `report_reference_decision` tool call.
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
-pub struct ReferenceFilterResponse {
-    answer: String,
+pub struct ReferenceDecision {
+    change_required: bool,
+    reason: String,
+    confidence: ReferenceDecisionConfidence,
 }
 
-impl ReferenceFilterResponse {
-    pub fn new(answer: &str) -> Self {
+impl ReferenceDecision {
+    pub fn new(
+        change_required: bool,
+        reason: String,
+        confidence: ReferenceDecisionConfidence,
+    ) -> Self {
         Self {
-            answer: answer.to_string(),
+            change_required,
+            reason,
+            confidence,
         }
     }
 
-    pub fn answer(&self) -> &str {
-        &self.answer
+    pub fn change_required(&self) -> bool {
+        self.change_required
+    }
+
+    pub fn reason(&self) -> &str {
+        &self.reason
     }
+
+    pub fn confidence(&self) -> ReferenceDecisionConfidence {
+        self.confidence
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct ReferenceFilterResponse {
+    decisions: Vec<ReferenceDecision>,
 }
 
+impl ReferenceFilterResponse {
+    pub fn new(decisions: Vec<ReferenceDecision>) -> Self {
+        Self { decisions }
+    }
+
+    pub fn decisions(&self) -> &[ReferenceDecision] {
+        &self.decisions
+    }
+}
+
+/// Name of the tool we ask the model to call when it supports structured
+/// tool-calling output.
+const REPORT_REFERENCE_DECISION_TOOL: &str = "report_reference_decision";
+
 pub struct ReferenceFilterBroker {
-    llm_client: Arc<LLMBroker>,
+    llm_client: Arc<dyn Broker>,
     _fail_over_llm: LLMProperties,
+    /// Operator-configured jq transform for the `"filter_references"` event,
+    /// if any. When absent we fall back to [`Self::parse_tool_call`]/
+    /// [`Self::parse_response`].
+    transform_pipeline: Option<Arc<TransformPipeline>>,
 }
 
 impl ReferenceFilterBroker {
-    pub fn new(llm_client: Arc<LLMBroker>, fail_over_llm: LLMProperties) -> Self {
+    pub fn new(llm_client: Arc<dyn Broker>, fail_over_llm: LLMProperties) -> Self {
         Self {
             llm_client,
             _fail_over_llm: fail_over_llm,
+            transform_pipeline: None,
         }
     }
 
+    pub fn with_transform_pipeline(mut self, transform_pipeline: Arc<TransformPipeline>) -> Self {
+        self.transform_pipeline = Some(transform_pipeline);
+        self
+    }
+
+    /// The tool schema for `report_reference_decision`, used for providers
+    /// which support function/tool calling. Keeping this next to the XML
+    /// prompt makes it easy to keep both in sync.
+    fn report_reference_decision_tool() -> LLMClientToolSchema {
+        LLMClientToolSchema::new(
+            REPORT_REFERENCE_DECISION_TOOL,
+            "Report whether the reference needs to change because of the user's edit, along with the reason and your confidence.",
+            serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "change_required": {
+                        "type": "boolean",
+                        "description": "true if the reference must change because of the edit to the selected code"
+                    },
+                    "reason": {
+                        "type": "string",
+                        "description": "a single, high value sentence explaining WHY the reference needs (or does not need) to change"
+                    },
+                    "confidence": {
+                        "type": "string",
+                        "enum": ["low", "medium", "high"],
+                        "description": "how confident you are in this decision"
+                    }
+                },
+                "required": ["change_required", "reason", "confidence"]
+            }),
+        )
+    }
+
     pub fn later_system_message(&self) -> String {
         format!(
             r#"You are an expert software engineer. 
@@ -123,7 +211,32 @@ Omit those that do not need to change.
         )
     }
 
+    /// The tool-calling system message. This is the primary path: we ask the
+    /// model to report its decision through `report_reference_decision`
+    /// instead of scraping XML tags out of free text.
+    pub fn tool_system_message(&self) -> String {
+        format!(
+            r#"You are an expert software engineer who is pair programming with another developer.
+- The developer who you are helping with has selected some code which is present in <code_selected> and they intent to change it, the request for change will be provided to you in <user_query>.
+- We found a reference for the code present in <code_selected> which is given to you in <reference> section. This means that any change made to <code_selected> might also require changes to the <reference> section.
+- Given the changes which will be made to <code_selected> because of the <user_query> you need to decide if we need to change the code in <reference> section.
+- You MUST report your decision by calling the `{}` tool, do not reply in plain text.
+- <user_query> which CAN lead to additional changes:
+- - The user might be changing the function definition
+- - The user might be adding a new parameter or removing a parameter for the class
+- - Changing code from sync to async
+- - and many more such cases which changes the structure and the meaning of the code, as these can be breaking changes.
+- Making a change requires a lot of effort, so be very certain if we should change the code in our selection in <code_selected> based on the <user_query>
+- In your reason do not mention the <reference> as reference code, but instead talk about the code symbol.
+- Your reason MUST contain the "WHY" for the change. We MUST explain to the user why the code in <reference> might require a change."#,
+            REPORT_REFERENCE_DECISION_TOOL
+        )
+    }
+
     // consider variants: tiny, regular, in-depth
+    //
+    // The XML-format prompt whose reply `parse_response` reads. `invoke`
+    // sends `tool_system_message` instead and does not use this one.
     pub fn system_message(&self) -> String {
         format!(
             r#"You are an expert software engineer who is pair programming with another developer.
@@ -194,9 +307,18 @@ your single sentence
             .collect()
     }
 
-    pub fn parse_response(response: &str) -> String {
-        println!("parse_response::response: {}", response);
-        let answer = response
+    /// Parses the `report_reference_decision` tool-call arguments (a JSON
+    /// object matching [`ReferenceDecision`]) into the typed struct.
+    fn parse_tool_call(arguments: &str) -> Result<ReferenceDecision, ToolError> {
+        serde_json::from_str(arguments)
+            .map_err(|e| ToolError::SerdeConversionFailed(e.to_string()))
+    }
+
+    /// Fallback parser for providers without tool-calling support: scrapes
+    /// the `<reply>...</reply>` block and pulls `<reason>`/`<change_required>`
+    /// out of it by hand.
+    pub fn parse_response(response: &str) -> ReferenceDecision {
+        let reply = response
             .lines()
             .skip_while(|l| !l.contains("<reply>"))
             .skip(1)
@@ -204,7 +326,31 @@ your single sentence
             .collect::<Vec<&str>>()
             .join("\n");
 
-        answer
+        let reason = reply
+            .lines()
+            .skip_while(|l| !l.contains("<reason>"))
+            .skip(1)
+            .take_while(|l| !l.contains("</reason>"))
+            .collect::<Vec<&str>>()
+            .join("\n")
+            .trim()
+            .to_owned();
+
+        let change_required = reply
+            .lines()
+            .skip_while(|l| !l.contains("<change_required>"))
+            .skip(1)
+            .take_while(|l| !l.contains("</change_required>"))
+            .collect::<Vec<&str>>()
+            .join("\n")
+            .trim()
+            .eq_ignore_ascii_case("true");
+
+        ReferenceDecision::new(
+            change_required,
+            reason,
+            ReferenceDecisionConfidence::Medium,
+        )
     }
 }
 
@@ -215,25 +361,32 @@ impl Tool for ReferenceFilterBroker {
         let llm_properties = context.llm_properties.clone();
         let root_request_id = context.root_id.to_owned();
 
-        let system_message = LLMClientMessage::system(self.system_message());
+        // Every request carries the `report_reference_decision` tool schema
+        // and the tool-calling system message; the XML prompt from
+        // `system_message` is not sent from here. A reply without a parsable
+        // tool call still goes through the XML parser below.
+        let system_message = LLMClientMessage::system(self.tool_system_message());
+        let tools = vec![Self::report_reference_decision_tool()];
         let user_messages = self.user_message(&context);
 
-        let _ = stream::iter(user_messages.into_iter().map(|user_message| {
+        let decisions = stream::iter(user_messages.into_iter().map(|user_message| {
             (
                 LLMClientCompletionRequest::new(
                     llm_properties.llm().clone(),
                     vec![system_message.clone(), LLMClientMessage::user(user_message)],
                     0.2,
                     None,
-                ),
+                )
+                .with_tools(tools.clone()),
                 self.llm_client.clone(),
                 llm_properties.clone(),
                 root_request_id.to_owned(),
+                self.transform_pipeline.clone(),
             )
         }))
         .map(
-            |(request, llm_client, llm_properties, root_request_id)| async move {
-                let (sender, _receiver) = tokio::sync::mpsc::unbounded_channel();
+            |(request, llm_client, llm_properties, root_request_id, transform_pipeline)| async move {
+                let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel();
                 let response = llm_client
                     .stream_completion(
                         llm_properties.api_key().clone(),
@@ -248,22 +401,73 @@ impl Tool for ReferenceFilterBroker {
                         sender,
                     )
                     .await;
-                println!("reference_check::response::({:?})", response);
-            },
-        )
-        .buffer_unordered(200)
-        .collect::<Vec<_>>()
-        .await;
-
-        Err(ToolError::MissingTool)
 
-        // // this may need to become more sophisticated later, but we roll for now
-        // let answer = ReferenceFilterBroker::parse_response(&response);
+                // the chunk carrying the assembled tool-call arguments is the
+                // last one with a non-empty `tool_calls`, not necessarily the
+                // literal last chunk of the stream — a trailing usage-only
+                // chunk (e.g. from `stream_options.include_usage`) has empty
+                // `choices` and so carries no tool calls at all. `response`
+                // is only ever the accumulated plain-text buffer, which the
+                // XML fallback parser needs but a tool call does not live in.
+                let mut last_tool_call_chunk = None;
+                while let Ok(chunk) = receiver.try_recv() {
+                    if chunk.tool_calls().is_some_and(|calls| !calls.is_empty()) {
+                        last_tool_call_chunk = Some(chunk);
+                    }
+                }
+
+                let answer = response.ok()?;
+
+                // an operator-configured jq transform takes priority over
+                // everything else, so extraction can be adjusted per
+                // model without a code change
+                if let Some(decision) = transform_pipeline.as_ref().and_then(|pipeline| {
+                    pipeline
+                        .transform("filter_references", &serde_json::Value::String(answer.clone()))
+                        .and_then(|result| result.ok())
+                        .and_then(|value| serde_json::from_value::<ReferenceDecision>(value).ok())
+                }) {
+                    return Some(decision);
+                }
+
+                if let Some(tool_call) = last_tool_call_chunk
+                    .as_ref()
+                    .and_then(|chunk| chunk.tool_calls())
+                    .and_then(|calls| calls.first())
+                {
+                    if let Ok(decision) =
+                        ReferenceFilterBroker::parse_tool_call(tool_call.arguments())
+                    {
+                        return Some(decision);
+                    }
+                }
 
-        // println!("answer: {}", &answer);
+                // no tool call came back (or it didn't parse); assume this
+                // is the XML fallback reply instead.
+                Some(ReferenceFilterBroker::parse_response(&answer))
+            },
+        )
+        .buffered(200)
+        .collect::<Vec<Option<ReferenceDecision>>>()
+        .await
+        .into_iter()
+        .map(|decision| {
+            // one decision per `OutlineNode`, in the same order as
+            // `reference_outlines`; a node whose request failed still gets
+            // an explicit entry instead of silently disappearing from the
+            // result.
+            decision.unwrap_or_else(|| {
+                ReferenceDecision::new(
+                    false,
+                    "failed to get a decision from the model for this reference".to_owned(),
+                    ReferenceDecisionConfidence::Low,
+                )
+            })
+        })
+        .collect::<Vec<_>>();
 
-        // Ok(ToolOutput::ReferencesFilter(ReferenceFilterResponse::new(
-        //     &answer,
-        // )))
+        Ok(ToolOutput::ReferencesFilter(ReferenceFilterResponse::new(
+            decisions,
+        )))
     }
 }
\ No newline at end of file
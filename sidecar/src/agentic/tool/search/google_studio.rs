@@ -1,6 +1,6 @@
 use async_trait::async_trait;
 use llm_client::{
-    broker::LLMBroker,
+    broker::Broker,
     clients::types::{LLMClientCompletionRequest, LLMClientMessage},
 };
 use std::{sync::Arc, time::Instant};
@@ -8,26 +8,59 @@ use std::{sync::Arc, time::Instant};
 use crate::agentic::symbol::identifier::LLMProperties;
 
 use super::agentic::{GenerateSearchPlan, GenerateSearchPlanError, SearchPlanQuery};
+use super::memory_backend::{ContextSnippet, MemoryBackend};
 
 struct GoogleStudioPlanGenerator {
-    llm_client: Arc<LLMBroker>,
+    llm_client: Arc<dyn Broker>,
     _fail_over_llm: LLMProperties,
+    memory_backend: Arc<dyn MemoryBackend>,
 }
 
 impl GoogleStudioPlanGenerator {
-    pub fn new(llm_client: Arc<LLMBroker>, fail_over_llm: LLMProperties) -> Self {
+    pub fn new(
+        llm_client: Arc<dyn Broker>,
+        fail_over_llm: LLMProperties,
+        memory_backend: Arc<dyn MemoryBackend>,
+    ) -> Self {
         Self {
             llm_client,
             _fail_over_llm: fail_over_llm,
+            memory_backend,
         }
     }
 
-    fn system_message_for_keyword_search(&self, request: &SearchPlanQuery) -> String {
-        todo!()
+    fn system_message_for_keyword_search(&self, _request: &SearchPlanQuery) -> String {
+        format!(
+            r#"You are an expert software engineer helping to plan a keyword search over a codebase.
+
+You will be given a user query and a set of context snippets retrieved from the repository which are relevant to it.
+
+Use the context snippets to ground your plan in the actual code that exists, rather than guessing at file names or symbols which may not exist."#
+        )
     }
 
-    fn user_message_for_keyword_search(&self, request: &SearchPlanQuery) -> String {
-        todo!()
+    fn user_message_for_keyword_search(
+        &self,
+        request: &SearchPlanQuery,
+        context: &[ContextSnippet],
+    ) -> String {
+        let context_block = context
+            .iter()
+            .map(|snippet| format!("{}:\n{}", snippet.fs_file_path(), snippet.content()))
+            .collect::<Vec<_>>()
+            .join("\n\n");
+
+        format!(
+            r#"<user_query>
+{}
+</user_query>
+
+<context>
+{}
+</context>"#,
+            request.user_query(),
+            context_block
+        )
     }
 }
 
@@ -42,9 +75,23 @@ impl GenerateSearchPlan for GoogleStudioPlanGenerator {
         let provider = request.provider().clone();
         let api_keys = request.api_keys().clone();
 
+        let context = self
+            .memory_backend
+            .get_context(&request)
+            .await
+            .unwrap_or_else(|e| {
+                eprintln!(
+                    "google_studio::generate_search_plan: memory backend failed, falling back to an ungrounded plan: {:?}",
+                    e
+                );
+                Vec::new()
+            });
+
         let system_message =
             LLMClientMessage::system(self.system_message_for_keyword_search(&request));
-        let user_message = LLMClientMessage::user(self.user_message_for_keyword_search(&request));
+        let user_message = LLMClientMessage::user(
+            self.user_message_for_keyword_search(&request, &context),
+        );
         let messages = LLMClientCompletionRequest::new(
             model,
             vec![system_message.clone(), user_message.clone()],
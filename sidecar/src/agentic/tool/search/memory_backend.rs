@@ -0,0 +1,207 @@
+//! Pluggable retrieval backends used to ground a search plan in actual
+//! repository context instead of the bare query text.
+
+use async_trait::async_trait;
+use std::{path::PathBuf, sync::Arc};
+
+use super::agentic::SearchPlanQuery;
+
+#[derive(Debug, thiserror::Error)]
+pub enum MemoryBackendError {
+    #[error("io error: {0}")]
+    IoError(String),
+    #[error("postgres error: {0}")]
+    PostgresError(String),
+}
+
+/// A single piece of retrieved context, ready to be spliced into a prompt.
+#[derive(Debug, Clone)]
+pub struct ContextSnippet {
+    fs_file_path: String,
+    content: String,
+    score: f32,
+}
+
+impl ContextSnippet {
+    pub fn new(fs_file_path: String, content: String, score: f32) -> Self {
+        Self {
+            fs_file_path,
+            content,
+            score,
+        }
+    }
+
+    pub fn fs_file_path(&self) -> &str {
+        &self.fs_file_path
+    }
+
+    pub fn content(&self) -> &str {
+        &self.content
+    }
+
+    pub fn score(&self) -> f32 {
+        self.score
+    }
+}
+
+/// A source of grounding context for a [`SearchPlanQuery`]. Implementations
+/// can be backed by the filesystem, an in-memory vector store, or an
+/// external vector database - the plan generator does not need to know
+/// which.
+#[async_trait]
+pub trait MemoryBackend: Send + Sync {
+    async fn get_context(
+        &self,
+        query: &SearchPlanQuery,
+    ) -> Result<Vec<ContextSnippet>, MemoryBackendError>;
+}
+
+/// Returns file contents for paths matching a glob rooted at `root_directory`.
+/// This is the simplest possible backend and is mostly useful for small
+/// repositories or tests.
+pub struct FileStoreMemoryBackend {
+    root_directory: PathBuf,
+    glob: String,
+}
+
+impl FileStoreMemoryBackend {
+    pub fn new(root_directory: PathBuf, glob: String) -> Self {
+        Self {
+            root_directory,
+            glob,
+        }
+    }
+}
+
+#[async_trait]
+impl MemoryBackend for FileStoreMemoryBackend {
+    async fn get_context(
+        &self,
+        _query: &SearchPlanQuery,
+    ) -> Result<Vec<ContextSnippet>, MemoryBackendError> {
+        let pattern = self.root_directory.join(&self.glob);
+        let pattern = pattern.to_string_lossy().to_string();
+        let mut snippets = vec![];
+        for entry in glob::glob(&pattern).map_err(|e| MemoryBackendError::IoError(e.to_string()))?
+        {
+            let path = entry.map_err(|e| MemoryBackendError::IoError(e.to_string()))?;
+            let content = tokio::fs::read_to_string(&path)
+                .await
+                .map_err(|e| MemoryBackendError::IoError(e.to_string()))?;
+            snippets.push(ContextSnippet::new(
+                path.to_string_lossy().to_string(),
+                content,
+                1.0,
+            ));
+        }
+        Ok(snippets)
+    }
+}
+
+/// Embeds outline nodes/snippets up-front and does a brute-force
+/// cosine-similarity top-k search at query time. Good enough for repositories
+/// which comfortably fit in memory.
+pub struct VectorStoreMemoryBackend {
+    embedder: Arc<dyn Fn(&str) -> Vec<f32> + Send + Sync>,
+    entries: Vec<(ContextSnippet, Vec<f32>)>,
+    top_k: usize,
+}
+
+impl VectorStoreMemoryBackend {
+    pub fn new(embedder: Arc<dyn Fn(&str) -> Vec<f32> + Send + Sync>, top_k: usize) -> Self {
+        Self {
+            embedder,
+            entries: vec![],
+            top_k,
+        }
+    }
+
+    pub fn add_snippet(&mut self, fs_file_path: String, content: String) {
+        let embedding = (self.embedder)(&content);
+        self.entries
+            .push((ContextSnippet::new(fs_file_path, content, 0.0), embedding));
+    }
+
+    fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+        let dot: f32 = a.iter().zip(b.iter()).map(|(x, y)| x * y).sum();
+        let norm_a: f32 = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+        let norm_b: f32 = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+        if norm_a == 0.0 || norm_b == 0.0 {
+            0.0
+        } else {
+            dot / (norm_a * norm_b)
+        }
+    }
+}
+
+#[async_trait]
+impl MemoryBackend for VectorStoreMemoryBackend {
+    async fn get_context(
+        &self,
+        query: &SearchPlanQuery,
+    ) -> Result<Vec<ContextSnippet>, MemoryBackendError> {
+        let query_embedding = (self.embedder)(query.user_query());
+        let mut scored = self
+            .entries
+            .iter()
+            .map(|(snippet, embedding)| {
+                let score = Self::cosine_similarity(&query_embedding, embedding);
+                ContextSnippet::new(
+                    snippet.fs_file_path().to_owned(),
+                    snippet.content().to_owned(),
+                    score,
+                )
+            })
+            .collect::<Vec<_>>();
+        scored.sort_by(|a, b| b.score().total_cmp(&a.score()));
+        scored.truncate(self.top_k);
+        Ok(scored)
+    }
+}
+
+/// Backs retrieval with a table in an external Postgres instance, for
+/// repositories too large to comfortably hold in memory. Rows are ranked by
+/// the `pg_trgm` trigram `similarity(content, query)`; no embeddings are
+/// computed or compared, so the pgvector extension is not used.
+pub struct PgVectorMemoryBackend {
+    pool: sqlx::PgPool,
+    table_name: String,
+    top_k: i64,
+}
+
+impl PgVectorMemoryBackend {
+    pub fn new(pool: sqlx::PgPool, table_name: String, top_k: i64) -> Self {
+        Self {
+            pool,
+            table_name,
+            top_k,
+        }
+    }
+}
+
+#[async_trait]
+impl MemoryBackend for PgVectorMemoryBackend {
+    async fn get_context(
+        &self,
+        query: &SearchPlanQuery,
+    ) -> Result<Vec<ContextSnippet>, MemoryBackendError> {
+        // Rows are ranked by trigram similarity between their `content` and
+        // the query text; no embedding of the query is computed here.
+        let query_text = query.user_query();
+        let rows: Vec<(String, String, f32)> = sqlx::query_as(&format!(
+            "SELECT fs_file_path, content, similarity(content, $1) AS score \
+             FROM {} ORDER BY score DESC LIMIT $2",
+            self.table_name
+        ))
+        .bind(query_text)
+        .bind(self.top_k)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| MemoryBackendError::PostgresError(e.to_string()))?;
+
+        Ok(rows
+            .into_iter()
+            .map(|(fs_file_path, content, score)| ContextSnippet::new(fs_file_path, content, score))
+            .collect())
+    }
+}
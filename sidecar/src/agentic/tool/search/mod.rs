@@ -0,0 +1,2 @@
+mod google_studio;
+pub mod memory_backend;
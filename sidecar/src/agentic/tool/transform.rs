@@ -0,0 +1,93 @@
+//! Configurable response-transform pipeline for LLM broker outputs.
+//!
+//! Every broker used to hand-roll its own parsing of the raw LLM response
+//! (see `ReferenceFilterBroker::parse_response`), which meant adding a new
+//! model or prompt shape forced a code change. Instead, each tool/event can
+//! attach a named jq program which maps the raw response (wrapped as a
+//! [`serde_json::Value`]) into the shape the tool expects. Programs are keyed
+//! by `event_type` - the same string already threaded through broker metadata
+//! (e.g. `"filter_references"`, `"keyword_search"`) - so operators can adjust
+//! extraction per model without recompiling.
+
+use serde_json::Value;
+use std::{collections::HashMap, sync::Mutex};
+
+#[derive(Debug, thiserror::Error)]
+pub enum TransformError {
+    #[error("failed to compile jq program for {context_label}: {source}")]
+    CompileFailed {
+        context_label: String,
+        source: String,
+    },
+    #[error("failed to run jq program for {context_label}: {source}")]
+    RunFailed {
+        context_label: String,
+        source: String,
+    },
+}
+
+/// Compiles `program` and runs it against `input`, returning the first
+/// output value. This is the low-level building block used both for one-off
+/// transforms and by [`TransformPipeline`], which precompiles programs so
+/// the hot path never pays the compile cost.
+pub fn jq_compile(input: &Value, program: &str, context_label: String) -> Result<Value, TransformError> {
+    let mut compiled = jq_rs::compile(program).map_err(|e| TransformError::CompileFailed {
+        context_label: context_label.clone(),
+        source: e.to_string(),
+    })?;
+    let input_str = input.to_string();
+    let output = compiled
+        .run(&input_str)
+        .map_err(|e| TransformError::RunFailed {
+            context_label,
+            source: e.to_string(),
+        })?;
+    serde_json::from_str(&output).map_err(|e| TransformError::RunFailed {
+        context_label: "jq output was not valid json".to_owned(),
+        source: e.to_string(),
+    })
+}
+
+/// Holds one compiled jq program per `event_type`, compiled once at startup
+/// from the programs configured on `LLMClientConfig`.
+pub struct TransformPipeline {
+    programs: HashMap<String, Mutex<jq_rs::JqProgram>>,
+}
+
+impl TransformPipeline {
+    /// Compiles every `(event_type, program)` pair up front so a bad program
+    /// is caught at startup rather than on the first request that needs it.
+    pub fn compile(programs: &HashMap<String, String>) -> Result<Self, TransformError> {
+        let mut compiled = HashMap::new();
+        for (event_type, program) in programs {
+            let program = jq_rs::compile(program).map_err(|e| TransformError::CompileFailed {
+                context_label: event_type.clone(),
+                source: e.to_string(),
+            })?;
+            compiled.insert(event_type.clone(), Mutex::new(program));
+        }
+        Ok(Self { programs: compiled })
+    }
+
+    /// Runs the program configured for `event_type` against `input`, if one
+    /// is configured. Returns `None` when no program is configured for this
+    /// event, so callers can fall back to their hard-coded parser.
+    pub fn transform(&self, event_type: &str, input: &Value) -> Option<Result<Value, TransformError>> {
+        let program = self.programs.get(event_type)?;
+        let mut program = program.lock().expect("transform pipeline mutex poisoned");
+        let input_str = input.to_string();
+        let result = program
+            .run(&input_str)
+            .map_err(|e| TransformError::RunFailed {
+                context_label: event_type.to_owned(),
+                source: e.to_string(),
+            })
+            .and_then(|output| {
+                serde_json::from_str(&output).map_err(|e| TransformError::RunFailed {
+                    context_label: event_type.to_owned(),
+                    source: e.to_string(),
+                })
+            });
+        Some(result)
+    }
+}
@@ -7,42 +7,140 @@ use llm_client::{
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+use crate::agentic::tool::transform::{TransformError, TransformPipeline};
+
+/// Current `LLMClientConfig` schema version. Bump this whenever the shape of
+/// `available_models` changes in a way that isn't backwards compatible.
+const CURRENT_CONFIG_VERSION: u32 = 2;
+
+/// Which model role a caller wants resolved. `slow_model`/`fast_model` are
+/// the only roles today, but this is the seam future roles (e.g. a
+/// reasoning-only model) hang off without another breaking config change.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Role {
+    Slow,
+    Fast,
+}
+
+/// A single entry in the flat model registry. Unlike the old nested
+/// `HashMap<LLMType, Model>`, provider-specific knobs (top_p, stop
+/// sequences, Azure deployment quirks, reasoning-effort flags, ...) are kept
+/// per entry in `extra`. `extra` and `name` are only stored and
+/// (de)serialized: nothing forwards them into a provider request, and the
+/// upstream model string a request is sent with comes from
+/// `OpenAIClient::model`, not from `name`.
 #[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ModelEntry {
+    /// The logical model identifier this entry answers to; this is what
+    /// `slow_model`/`fast_model` are matched against.
+    pub model: LLMType,
+    pub provider: LLMProvider,
+    /// The upstream model name/deployment id for this entry; not read when
+    /// building provider requests.
+    pub name: String,
+    pub max_tokens: u32,
+    #[serde(default)]
+    pub extra: serde_json::Value,
+}
+
+#[derive(Clone, Debug, Serialize)]
 pub struct LLMClientConfig {
+    #[serde(default = "current_config_version")]
+    pub version: u32,
     pub slow_model: LLMType,
     pub fast_model: LLMType,
-    pub models: HashMap<LLMType, Model>,
+    pub available_models: Vec<ModelEntry>,
     pub providers: Vec<LLMProviderAPIKeys>,
+    /// jq programs which transform a broker's raw response into the typed
+    /// shape it expects, keyed by `event_type` (e.g. `"filter_references"`).
+    /// Absent when an operator hasn't configured one for an event, in which
+    /// case the broker falls back to its hard-coded parser.
+    #[serde(default)]
+    pub response_transforms: HashMap<String, String>,
+}
+
+fn current_config_version() -> u32 {
+    CURRENT_CONFIG_VERSION
 }
 
 impl LLMClientConfig {
-    pub fn provider_for_slow_model(&self) -> Option<&LLMProviderAPIKeys> {
-        // we first need to get the model configuration for the slow model
-        // which will give us the model and the context around it
-        let model = self.models.get(&self.slow_model);
-        if let None = model {
-            return None;
+    fn llm_type_for_role(&self, role: Role) -> &LLMType {
+        match role {
+            Role::Slow => &self.slow_model,
+            Role::Fast => &self.fast_model,
         }
-        let model = model.expect("is_none above to hold");
-        let provider = &model.provider;
-        // get the related provider if its present
-        self.providers.iter().find(|p| p.key(provider).is_some())
+    }
+
+    /// Resolves the registry entry for `role` against the flat
+    /// `available_models` list.
+    pub fn model_for_role(&self, role: Role) -> Option<&ModelEntry> {
+        let llm_type = self.llm_type_for_role(role);
+        self.available_models
+            .iter()
+            .find(|model| &model.model == llm_type)
+    }
+
+    pub fn provider_for_role(&self, role: Role) -> Option<&LLMProviderAPIKeys> {
+        let model = self.model_for_role(role)?;
+        self.providers
+            .iter()
+            .find(|p| p.key(&model.provider).is_some())
+    }
+
+    pub fn provider_for_slow_model(&self) -> Option<&LLMProviderAPIKeys> {
+        self.provider_for_role(Role::Slow)
     }
 
     pub fn provider_for_fast_model(&self) -> Option<&LLMProviderAPIKeys> {
-        // we first need to get the model configuration for the slow model
-        // which will give us the model and the context around it
-        let model = self.models.get(&self.fast_model);
-        if let None = model {
-            return None;
+        self.provider_for_role(Role::Fast)
+    }
+
+    /// Compiles the configured `response_transforms` once, so the resulting
+    /// [`TransformPipeline`] can be shared across brokers without paying the
+    /// jq compile cost on every request.
+    pub fn compile_transform_pipeline(&self) -> Result<TransformPipeline, TransformError> {
+        TransformPipeline::compile(&self.response_transforms)
+    }
+
+    /// Migrates the pre-v2, nested `{ models: HashMap<LLMType, Model> }`
+    /// shape into the flat `available_models` registry, so old configs on
+    /// disk keep deserializing without an operator-visible migration step.
+    fn from_legacy(legacy: LegacyLLMClientConfig) -> Self {
+        let available_models = legacy
+            .models
+            .into_iter()
+            .map(|(llm_type, model)| ModelEntry {
+                name: format!("{:?}", llm_type),
+                model: llm_type,
+                provider: model.provider,
+                max_tokens: model.context_length,
+                extra: serde_json::json!({ "temperature": model.temperature }),
+            })
+            .collect();
+        Self {
+            version: 1,
+            slow_model: legacy.slow_model,
+            fast_model: legacy.fast_model,
+            available_models,
+            providers: legacy.providers,
+            response_transforms: legacy.response_transforms,
         }
-        let model = model.expect("is_none above to hold");
-        let provider = &model.provider;
-        // get the related provider if its present
-        self.providers.iter().find(|p| p.key(provider).is_some())
     }
 }
 
+/// The pre-v2 shape of [`LLMClientConfig`], kept around only so old
+/// configuration on disk keeps loading. New configs should be written in the
+/// flat `available_models` shape instead.
+#[derive(Clone, Debug, Deserialize)]
+struct LegacyLLMClientConfig {
+    slow_model: LLMType,
+    fast_model: LLMType,
+    models: HashMap<LLMType, Model>,
+    providers: Vec<LLMProviderAPIKeys>,
+    #[serde(default)]
+    response_transforms: HashMap<String, String>,
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct Model {
     pub context_length: u32,
@@ -50,9 +148,51 @@ pub struct Model {
     pub provider: LLMProvider,
 }
 
+/// Tries the current flat shape first and falls back to the legacy nested
+/// shape, migrating it on the fly. This is the seam that lets
+/// `LLMClientConfig` stay backwards compatible without a separate CLI
+/// migration step.
+impl<'de> serde::Deserialize<'de> for LLMClientConfig {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Raw {
+            Flat(FlatLLMClientConfig),
+            Legacy(LegacyLLMClientConfig),
+        }
+
+        #[derive(Deserialize)]
+        struct FlatLLMClientConfig {
+            #[serde(default = "current_config_version")]
+            version: u32,
+            slow_model: LLMType,
+            fast_model: LLMType,
+            available_models: Vec<ModelEntry>,
+            providers: Vec<LLMProviderAPIKeys>,
+            #[serde(default)]
+            response_transforms: HashMap<String, String>,
+        }
+
+        match Raw::deserialize(deserializer)? {
+            Raw::Flat(flat) => Ok(LLMClientConfig {
+                version: flat.version,
+                slow_model: flat.slow_model,
+                fast_model: flat.fast_model,
+                available_models: flat.available_models,
+                providers: flat.providers,
+                response_transforms: flat.response_transforms,
+            }),
+            Raw::Legacy(legacy) => Ok(LLMClientConfig::from_legacy(legacy)),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::LLMClientConfig;
+    use super::{LLMClientConfig, Role};
 
     #[test]
     fn test_json_should_convert_properly() {
@@ -80,4 +220,84 @@ mod tests {
         "#;
         assert!(serde_json::from_str::<LLMClientConfig>(data).is_ok());
     }
+
+    #[test]
+    fn test_response_transforms_defaults_to_empty() {
+        let data = r#"
+        {
+			"slow_model": "GPT3_5_16k",
+			"fast_model": "GPT3_5_16k",
+			"models": {
+				"GPT3_5_16k": {
+					"context_length": 16000,
+					"temperature": 0.2,
+					"provider": "Azure"
+				}
+			},
+			"providers": []
+		}
+        "#;
+        let config = serde_json::from_str::<LLMClientConfig>(data).expect("should deserialize");
+        assert!(config.response_transforms.is_empty());
+    }
+
+    #[test]
+    fn test_legacy_config_migrates_to_flat_available_models() {
+        let data = r#"
+        {
+			"slow_model": "GPT3_5_16k",
+			"fast_model": "GPT3_5_16k",
+			"models": {
+				"GPT3_5_16k": {
+					"context_length": 16000,
+					"temperature": 0.2,
+					"provider": "Azure"
+				}
+			},
+			"providers": []
+		}
+        "#;
+        let config = serde_json::from_str::<LLMClientConfig>(data).expect("should deserialize");
+        assert_eq!(config.version, 1);
+        assert_eq!(config.available_models.len(), 1);
+        assert_eq!(config.available_models[0].max_tokens, 16000);
+    }
+
+    #[test]
+    fn test_flat_config_resolves_model_for_role() {
+        let data = r#"
+        {
+			"version": 2,
+			"slow_model": "GPT3_5_16k",
+			"fast_model": "Gpt4O",
+			"available_models": [
+				{
+					"model": "GPT3_5_16k",
+					"provider": "Azure",
+					"name": "gpt-35-turbo-16k",
+					"max_tokens": 16000,
+					"extra": {}
+				},
+				{
+					"model": "Gpt4O",
+					"provider": "OpenAI",
+					"name": "gpt-4o",
+					"max_tokens": 128000,
+					"extra": { "top_p": 0.9 }
+				}
+			],
+			"providers": []
+		}
+        "#;
+        let config = serde_json::from_str::<LLMClientConfig>(data).expect("should deserialize");
+        assert_eq!(config.version, 2);
+        assert_eq!(
+            config.model_for_role(Role::Fast).map(|m| m.name.as_str()),
+            Some("gpt-4o")
+        );
+        assert_eq!(
+            config.model_for_role(Role::Slow).map(|m| m.name.as_str()),
+            Some("gpt-35-turbo-16k")
+        );
+    }
 }